@@ -33,16 +33,21 @@
 //! ```
 
 use {
-    failure::Error,
-    futures::prelude::*,
+    failure::{format_err, Error},
+    futures::{future, prelude::*, stream},
     lazy_static::lazy_static,
     regex::Regex,
     reqwest::{r#async::Client, Url},
     scraper::{Html, Selector},
+    serde::{Deserialize, Serialize},
+    serde_json::Value,
     std::fmt,
 };
 
-#[derive(Clone, Debug)]
+#[cfg(feature = "server")]
+pub mod server;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AirStatus {
     pub station_address: String,
     pub time: String,
@@ -58,7 +63,16 @@ impl IntoIterator for AirStatus {
     }
 }
 
-#[derive(Clone, Debug)]
+impl AirStatus {
+    /// The overall CAI (Comprehensive Air-quality Index), computed as the
+    /// maximum index among all pollutants, matching how Airkorea derives the
+    /// station's overall grade from its individual pollutants.
+    pub fn overall_index(&self) -> Option<u32> {
+        self.pollutants.iter().filter_map(Pollutant::aqi).max()
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Pollutant {
     pub name: String,
     pub unit: String,
@@ -83,7 +97,105 @@ impl fmt::Display for Pollutant {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct AqiBreakpoint {
+    conc_low: f32,
+    conc_high: f32,
+    index_low: u32,
+    index_high: u32,
+}
+
+impl Pollutant {
+    /// Computes a normalized air-quality index (CAI) from the most recent
+    /// measured concentration, using piecewise-linear interpolation between
+    /// Airkorea's breakpoint table.
+    ///
+    /// Returns `None` if there is no measured value yet, or if this
+    /// pollutant's name is not one of the indexed pollutants (`PM10`,
+    /// `PM25`, `O3`, `NO2`, `CO`, `SO2`). A concentration that falls in the
+    /// gap between two segments of the table (e.g. a `PM10` value of 30.5)
+    /// also yields `None`.
+    pub fn aqi(&self) -> Option<u32> {
+        let conc = self.data.iter().rev().find_map(|d| *d)?;
+        let breakpoints = AQI_BREAKPOINTS.get(self.name.as_str())?;
+
+        let top = breakpoints.last()?;
+        if conc > top.conc_high {
+            return Some(top.index_high);
+        }
+
+        let bp = breakpoints
+            .iter()
+            .find(|bp| conc >= bp.conc_low && conc <= bp.conc_high)?;
+
+        let index = (bp.index_high - bp.index_low) as f32 / (bp.conc_high - bp.conc_low)
+            * (conc - bp.conc_low)
+            + bp.index_low as f32;
+        Some(index.round() as u32)
+    }
+}
+
+lazy_static! {
+    static ref AQI_BREAKPOINTS: std::collections::HashMap<&'static str, Vec<AqiBreakpoint>> = {
+        let mut m = std::collections::HashMap::new();
+        m.insert(
+            "PM10",
+            vec![
+                AqiBreakpoint { conc_low: 0.0, conc_high: 30.0, index_low: 0, index_high: 50 },
+                AqiBreakpoint { conc_low: 31.0, conc_high: 80.0, index_low: 51, index_high: 100 },
+                AqiBreakpoint { conc_low: 81.0, conc_high: 150.0, index_low: 101, index_high: 250 },
+                AqiBreakpoint { conc_low: 151.0, conc_high: 600.0, index_low: 251, index_high: 500 },
+            ],
+        );
+        m.insert(
+            "PM25",
+            vec![
+                AqiBreakpoint { conc_low: 0.0, conc_high: 15.0, index_low: 0, index_high: 50 },
+                AqiBreakpoint { conc_low: 16.0, conc_high: 35.0, index_low: 51, index_high: 100 },
+                AqiBreakpoint { conc_low: 36.0, conc_high: 75.0, index_low: 101, index_high: 250 },
+                AqiBreakpoint { conc_low: 76.0, conc_high: 500.0, index_low: 251, index_high: 500 },
+            ],
+        );
+        m.insert(
+            "O3",
+            vec![
+                AqiBreakpoint { conc_low: 0.0, conc_high: 0.030, index_low: 0, index_high: 50 },
+                AqiBreakpoint { conc_low: 0.031, conc_high: 0.090, index_low: 51, index_high: 100 },
+                AqiBreakpoint { conc_low: 0.091, conc_high: 0.150, index_low: 101, index_high: 250 },
+                AqiBreakpoint { conc_low: 0.151, conc_high: 0.600, index_low: 251, index_high: 500 },
+            ],
+        );
+        m.insert(
+            "NO2",
+            vec![
+                AqiBreakpoint { conc_low: 0.0, conc_high: 0.030, index_low: 0, index_high: 50 },
+                AqiBreakpoint { conc_low: 0.031, conc_high: 0.060, index_low: 51, index_high: 100 },
+                AqiBreakpoint { conc_low: 0.061, conc_high: 0.200, index_low: 101, index_high: 250 },
+                AqiBreakpoint { conc_low: 0.201, conc_high: 2.000, index_low: 251, index_high: 500 },
+            ],
+        );
+        m.insert(
+            "CO",
+            vec![
+                AqiBreakpoint { conc_low: 0.0, conc_high: 2.0, index_low: 0, index_high: 50 },
+                AqiBreakpoint { conc_low: 2.01, conc_high: 9.0, index_low: 51, index_high: 100 },
+                AqiBreakpoint { conc_low: 9.01, conc_high: 15.0, index_low: 101, index_high: 250 },
+                AqiBreakpoint { conc_low: 15.01, conc_high: 50.0, index_low: 251, index_high: 500 },
+            ],
+        );
+        m.insert(
+            "SO2",
+            vec![
+                AqiBreakpoint { conc_low: 0.0, conc_high: 0.020, index_low: 0, index_high: 50 },
+                AqiBreakpoint { conc_low: 0.021, conc_high: 0.050, index_low: 51, index_high: 100 },
+                AqiBreakpoint { conc_low: 0.051, conc_high: 0.150, index_low: 101, index_high: 250 },
+                AqiBreakpoint { conc_low: 0.151, conc_high: 1.000, index_low: 251, index_high: 500 },
+            ],
+        );
+        m
+    };
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Grade {
     None,
     Good,
@@ -128,16 +240,47 @@ fn extract_text_from_element(element: scraper::element_ref::ElementRef) -> Strin
     element.text().map(str::trim).collect::<Vec<_>>().join("")
 }
 
-fn request(url: Url) -> impl Future<Item = Html, Error = Error> {
-    let client = Client::new();
+/// Decodes a response body according to its (optional) `Content-Encoding`,
+/// falling back to treating the bytes as raw, uncompressed text when the
+/// encoding is absent or unrecognized.
+fn decode_body(content_encoding: Option<&str>, bytes: &[u8]) -> Result<String, Error> {
+    use std::io::Read;
+
+    let body = match content_encoding {
+        Some("gzip") => {
+            let mut decoded = Vec::new();
+            flate2::read::GzDecoder::new(bytes).read_to_end(&mut decoded)?;
+            String::from_utf8_lossy(&decoded).to_string()
+        }
+        Some("deflate") => {
+            let mut decoded = Vec::new();
+            flate2::read::DeflateDecoder::new(bytes).read_to_end(&mut decoded)?;
+            String::from_utf8_lossy(&decoded).to_string()
+        }
+        Some("br") => {
+            let mut decoded = Vec::new();
+            brotli::Decompressor::new(bytes, 4096).read_to_end(&mut decoded)?;
+            String::from_utf8_lossy(&decoded).to_string()
+        }
+        _ => String::from_utf8_lossy(bytes).to_string(),
+    };
+    Ok(body)
+}
+
+fn request(client: &Client, url: Url) -> impl Future<Item = Html, Error = Error> {
     client
         .get(url)
+        .header(reqwest::header::ACCEPT_ENCODING, "gzip, deflate, br")
         .send()
         .map_err(Into::into)
         .and_then(|resp| {
-            resp.into_body().concat2().map_err(Into::into).map(|chunk| {
-                let v = chunk.to_vec();
-                String::from_utf8_lossy(&v).to_string()
+            let content_encoding = resp
+                .headers()
+                .get(reqwest::header::CONTENT_ENCODING)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string);
+            resp.into_body().concat2().map_err(Into::into).and_then(move |chunk| {
+                decode_body(content_encoding.as_ref().map(String::as_str), &chunk)
             })
         })
         .map(|body| Html::parse_document(&body))
@@ -153,7 +296,7 @@ fn parse(document: &Html) -> AirStatus {
         static ref SELECTOR_GRADE: Selector = Selector::parse(".con>.co>.tx>.t1").unwrap();
         static ref SELECTOR_UNIT: Selector = Selector::parse(".con>.co>.tx>.t1>sub").unwrap();
         static ref SELECTOR_SCRIPT: Selector = Selector::parse("body>script:last-child").unwrap();
-        static ref REGEX_ROW: Regex = Regex::new(r"addRows\(\[(.*)\]\);").unwrap();
+        static ref REGEX_ADD_ROWS: Regex = Regex::new(r"(?s)addRows\((\[.*?\])\);").unwrap();
     }
 
     let station_address = document
@@ -198,12 +341,18 @@ fn parse(document: &Html) -> AirStatus {
         .next()
         .map(extract_text_from_element)
         .map(|script| {
-            REGEX_ROW
-                .find_iter(&script)
-                .map(|row| {
-                    let row = row.as_str();
-                    row.split("],[")
-                        .map(|data| data.split(',').filter_map(|s| s.parse::<f32>().ok()).next())
+            REGEX_ADD_ROWS
+                .captures_iter(&script)
+                .filter_map(|c| c.get(1).map(|m| m.as_str().to_string()))
+                .map(|raw_rows| {
+                    hjson2json::convert(&raw_rows)
+                        .ok()
+                        .and_then(|json| serde_json::from_str::<Vec<Vec<Value>>>(&json).ok())
+                        .unwrap_or_default()
+                })
+                .map(|rows| {
+                    rows.iter()
+                        .map(|row| row.iter().find_map(Value::as_f64).map(|v| v as f32))
                         .collect::<Vec<_>>()
                 })
                 .zip(pollutant_keys)
@@ -226,12 +375,12 @@ fn parse(document: &Html) -> AirStatus {
     }
 }
 
-pub fn search(longitude: f32, latitude: f32) -> impl Future<Item = AirStatus, Error = Error> {
+fn build_url(longitude: f32, latitude: f32) -> Url {
     static AIRKOREA_URL: &'static str = "http://m.airkorea.or.kr/main?deviceID=1234";
 
     let airkorea_url = std::env::var("AIRKOREA_URL").unwrap_or_else(|_| AIRKOREA_URL.to_string());
 
-    let addr = Url::parse_with_params(
+    Url::parse_with_params(
         &airkorea_url,
         &[
             ("lng", longitude.to_string()),
@@ -243,8 +392,183 @@ pub fn search(longitude: f32, latitude: f32) -> impl Future<Item = AirStatus, Er
             "Cannot parse url {}&lng={}&lat={}: {}",
             AIRKOREA_URL, longitude, latitude, why
         )
-    });
-    request(addr).map(|html| parse(&html))
+    })
+}
+
+pub fn search(longitude: f32, latitude: f32) -> impl Future<Item = AirStatus, Error = Error> {
+    let client = Client::new();
+    request(&client, build_url(longitude, latitude)).map(|html| parse(&html))
+}
+
+/// Searches many coordinates concurrently, reusing a single `reqwest` client
+/// (and its connection pool) instead of constructing one per request, and
+/// limiting in-flight requests to `CONCURRENCY` at a time.
+pub fn search_many(
+    coords: impl IntoIterator<Item = (f32, f32)>,
+) -> impl Stream<Item = AirStatus, Error = Error> {
+    static CONCURRENCY: usize = 8;
+
+    let client = Client::new();
+    let coords = coords.into_iter().collect::<Vec<_>>();
+    stream::iter_ok::<_, Error>(coords)
+        .map(move |(longitude, latitude)| {
+            request(&client, build_url(longitude, latitude)).map(|html| parse(&html))
+        })
+        .buffer_unordered(CONCURRENCY)
+}
+
+enum ConditionalResponse {
+    NotModified,
+    Modified {
+        html: Html,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+}
+
+fn conditional_request(
+    client: &Client,
+    url: Url,
+    etag: Option<String>,
+    last_modified: Option<String>,
+) -> impl Future<Item = ConditionalResponse, Error = Error> {
+    let mut request = client
+        .get(url)
+        .header(reqwest::header::ACCEPT_ENCODING, "gzip, deflate, br");
+    if let Some(etag) = etag {
+        request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+    }
+    if let Some(last_modified) = last_modified {
+        request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
+    }
+
+    request.send().map_err(Into::into).and_then(|resp| {
+        if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+            return future::Either::A(future::ok(ConditionalResponse::NotModified));
+        }
+
+        let content_encoding = resp
+            .headers()
+            .get(reqwest::header::CONTENT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let etag = resp
+            .headers()
+            .get(reqwest::header::ETAG)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+        let last_modified = resp
+            .headers()
+            .get(reqwest::header::LAST_MODIFIED)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string);
+
+        future::Either::B(resp.into_body().concat2().map_err(Into::into).and_then(
+            move |chunk| {
+                let body = decode_body(content_encoding.as_ref().map(String::as_str), &chunk)?;
+                Ok(ConditionalResponse::Modified {
+                    html: Html::parse_document(&body),
+                    etag,
+                    last_modified,
+                })
+            },
+        ))
+    })
+}
+
+#[derive(Clone)]
+struct CacheEntry {
+    etag: Option<String>,
+    last_modified: Option<String>,
+    status: AirStatus,
+    cached_at: std::time::Instant,
+}
+
+type Cache = std::sync::Arc<std::sync::Mutex<std::collections::HashMap<(u32, u32), CacheEntry>>>;
+
+/// A reusable client for repeated [`search`] calls, optionally caching
+/// responses in memory using the `ETag`/`Last-Modified` headers Airkorea
+/// returns.
+///
+/// Unlike the bare [`search`] function, `AirkoreaClient` reuses a single
+/// `reqwest` client (and its connection pool) across requests.
+pub struct AirkoreaClient {
+    client: Client,
+    cache: Option<Cache>,
+    ttl: std::time::Duration,
+}
+
+impl AirkoreaClient {
+    /// Creates a client with caching disabled.
+    pub fn new() -> Self {
+        AirkoreaClient {
+            client: Client::new(),
+            cache: None,
+            ttl: std::time::Duration::default(),
+        }
+    }
+
+    /// Enables an in-memory cache of responses, keyed by coordinates, that
+    /// are considered fresh for `ttl` before being evicted and refetched
+    /// from scratch.
+    pub fn with_cache(mut self, ttl: std::time::Duration) -> Self {
+        self.cache = Some(Default::default());
+        self.ttl = ttl;
+        self
+    }
+
+    /// Equivalent to the free [`search`] function, but reuses this client's
+    /// connection pool and, if enabled, its cache: unchanged pages are
+    /// reported by Airkorea as `304 Not Modified` and are served from the
+    /// cache without re-parsing.
+    pub fn search(&self, longitude: f32, latitude: f32) -> impl Future<Item = AirStatus, Error = Error> {
+        let url = build_url(longitude, latitude);
+        let key = (longitude.to_bits(), latitude.to_bits());
+
+        let cached = self.cache.as_ref().and_then(|cache| {
+            let mut cache = cache.lock().unwrap();
+            cache.retain(|_, entry| entry.cached_at.elapsed() < self.ttl);
+            cache.get(&key).cloned()
+        });
+        let (etag, last_modified, stale_status) = match cached {
+            Some(entry) => (entry.etag, entry.last_modified, Some(entry.status)),
+            None => (None, None, None),
+        };
+
+        let cache = self.cache.clone();
+        conditional_request(&self.client, url, etag, last_modified).and_then(move |resp| match resp {
+            ConditionalResponse::NotModified => stale_status.ok_or_else(|| {
+                format_err!(
+                    "Airkorea responded 304 Not Modified but no fresh cache entry was available"
+                )
+            }),
+            ConditionalResponse::Modified {
+                html,
+                etag,
+                last_modified,
+            } => {
+                let status = parse(&html);
+                if let Some(cache) = cache {
+                    cache.lock().unwrap().insert(
+                        key,
+                        CacheEntry {
+                            etag,
+                            last_modified,
+                            status: status.clone(),
+                            cached_at: std::time::Instant::now(),
+                        },
+                    );
+                }
+                Ok(status)
+            }
+        })
+    }
+}
+
+impl Default for AirkoreaClient {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
@@ -279,7 +603,7 @@ mod tests {
         rt.spawn(server);
 
         let url = "http://localhost:12121".parse().unwrap();
-        let fut = request(url)
+        let fut = request(&Client::new(), url)
             .map(|resp| {
                 assert_eq!(resp, Html::parse_document(HTML));
             })
@@ -291,6 +615,148 @@ mod tests {
         called_receiver.try_recv().unwrap();
     }
 
+    lazy_static! {
+        static ref ENV_AIRKOREA_URL_MUTEX: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    }
+
+    #[test]
+    fn test_airkorea_client_serves_cache_on_304() {
+        static HTML: &'static str = "<html><body></body></html>";
+        static ETAG: &'static str = "\"abc123\"";
+
+        let call_count = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = call_count.clone();
+
+        let (shutdown_sender, shutdown_receiver) = futures::sync::oneshot::channel();
+        let mut rt = Runtime::new().unwrap();
+
+        let service = hyper::service::make_service_fn(move |_| {
+            let counter = counter.clone();
+            hyper::service::service_fn_ok(move |req| {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                let matches = req
+                    .headers()
+                    .get(hyper::header::IF_NONE_MATCH)
+                    .and_then(|v| v.to_str().ok())
+                    == Some(ETAG);
+                if matches {
+                    hyper::Response::builder()
+                        .status(304)
+                        .body(hyper::Body::empty())
+                        .unwrap()
+                } else {
+                    hyper::Response::builder()
+                        .header(hyper::header::ETAG, ETAG)
+                        .body(hyper::Body::from(HTML))
+                        .unwrap()
+                }
+            })
+        });
+
+        let server = Server::bind(&"0.0.0.0:12123".parse().unwrap())
+            .serve(service)
+            .with_graceful_shutdown(shutdown_receiver)
+            .map_err(|why| panic!("{}", why));
+        rt.spawn(server);
+
+        let env_lock = ENV_AIRKOREA_URL_MUTEX.lock().unwrap();
+        std::env::set_var("AIRKOREA_URL", "http://localhost:12123");
+
+        let client = AirkoreaClient::new().with_cache(std::time::Duration::from_secs(3600));
+        let first = rt.block_on(client.search(1.0, 2.0)).unwrap();
+        let second = rt.block_on(client.search(1.0, 2.0)).unwrap();
+
+        std::env::remove_var("AIRKOREA_URL");
+        drop(env_lock);
+        shutdown_sender.send(()).unwrap();
+
+        assert_eq!(first.station_address, second.station_address);
+        assert_eq!(call_count.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn test_airkorea_client_refetches_after_ttl_expires() {
+        static HTML: &'static str = "<html><body></body></html>";
+
+        let conditional_requests = std::sync::Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = conditional_requests.clone();
+
+        let (shutdown_sender, shutdown_receiver) = futures::sync::oneshot::channel();
+        let mut rt = Runtime::new().unwrap();
+
+        let service = hyper::service::make_service_fn(move |_| {
+            let counter = counter.clone();
+            hyper::service::service_fn_ok(move |req| {
+                if req.headers().get(hyper::header::IF_NONE_MATCH).is_some() {
+                    counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                }
+                hyper::Response::builder()
+                    .header(hyper::header::ETAG, "\"abc123\"")
+                    .body(hyper::Body::from(HTML))
+                    .unwrap()
+            })
+        });
+
+        let server = Server::bind(&"0.0.0.0:12124".parse().unwrap())
+            .serve(service)
+            .with_graceful_shutdown(shutdown_receiver)
+            .map_err(|why| panic!("{}", why));
+        rt.spawn(server);
+
+        let env_lock = ENV_AIRKOREA_URL_MUTEX.lock().unwrap();
+        std::env::set_var("AIRKOREA_URL", "http://localhost:12124");
+
+        // A zero TTL means every cache entry is immediately stale, so no
+        // conditional header should ever be sent.
+        let client = AirkoreaClient::new().with_cache(std::time::Duration::from_millis(0));
+        rt.block_on(client.search(1.0, 2.0)).unwrap();
+        rt.block_on(client.search(1.0, 2.0)).unwrap();
+
+        std::env::remove_var("AIRKOREA_URL");
+        drop(env_lock);
+        shutdown_sender.send(()).unwrap();
+
+        assert_eq!(
+            conditional_requests.load(std::sync::atomic::Ordering::SeqCst),
+            0
+        );
+    }
+
+    #[test]
+    fn test_airkorea_client_errors_instead_of_panicking_on_unexpected_304() {
+        let (shutdown_sender, shutdown_receiver) = futures::sync::oneshot::channel();
+        let mut rt = Runtime::new().unwrap();
+
+        let service = hyper::service::make_service_fn(|_| {
+            hyper::service::service_fn_ok(|_| {
+                hyper::Response::builder()
+                    .status(304)
+                    .body(hyper::Body::empty())
+                    .unwrap()
+            })
+        });
+
+        let server = Server::bind(&"0.0.0.0:12125".parse().unwrap())
+            .serve(service)
+            .with_graceful_shutdown(shutdown_receiver)
+            .map_err(|why| panic!("{}", why));
+        rt.spawn(server);
+
+        let env_lock = ENV_AIRKOREA_URL_MUTEX.lock().unwrap();
+        std::env::set_var("AIRKOREA_URL", "http://localhost:12125");
+
+        // This client has no cache, so it never has a stale status to fall
+        // back on; an unexpected 304 must surface as an `Error`, not panic.
+        let client = AirkoreaClient::new();
+        let result = rt.block_on(client.search(1.0, 2.0));
+
+        std::env::remove_var("AIRKOREA_URL");
+        drop(env_lock);
+        shutdown_sender.send(()).unwrap();
+
+        assert!(result.is_err());
+    }
+
     #[test]
     fn test_parse() {
         static HTML: &'static str = include_str!("../tests/test.html");
@@ -372,6 +838,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_parse_extracts_rows_via_hjson2json() {
+        static HTML: &'static str = r#"<html>
+<body>
+<h1><span class="tit">Test Station</span><span class="tim">2019-04-13 18시 기준</span></h1>
+<div class="mList1">
+<ul>
+<li>
+<div class="tit">미세먼지(PM10)</div>
+<div class="con"><div class="co"><div class="tx"><div class="t1">70<sub>㎍/㎥</sub></div></div></div></div>
+</li>
+</ul>
+</div>
+<script>
+google.visualization.addRows([["00시",74],["01시",68]]);
+</script>
+</body>
+</html>"#;
+
+        let html = Html::parse_document(HTML);
+        let status = parse(&html);
+
+        assert_eq!(&status.station_address, "Test Station");
+        assert_eq!(status.pollutants.len(), 1);
+        assert_eq!(&status.pollutants[0].name, "PM10");
+        assert_eq!(&status.pollutants[0].unit, "㎍/㎥");
+        assert_eq!(status.pollutants[0].data, vec![Some(74.0), Some(68.0)]);
+    }
+
     #[test]
     fn test_extract_text_from_element() {
         static HTML: &'static str = "<p>foo<span>bar<h1>baz</h1></span></p>";
@@ -382,4 +877,167 @@ mod tests {
 
         assert_eq!(&text, "foobarbaz");
     }
+
+    #[test]
+    fn test_decode_body_passes_through_uncompressed_bytes() {
+        let body = decode_body(None, "hello, world".as_bytes()).unwrap();
+        assert_eq!(body, "hello, world");
+    }
+
+    #[test]
+    fn test_decode_body_does_not_error_on_invalid_utf8() {
+        let invalid = vec![0xff, 0xfe, 0xfd];
+        let body = decode_body(None, &invalid).unwrap();
+        assert_eq!(body, String::from_utf8_lossy(&invalid));
+    }
+
+    #[test]
+    fn test_decode_body_decodes_gzip() {
+        use std::io::Write;
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all("hello, gzip".as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!(decode_body(Some("gzip"), &compressed).unwrap(), "hello, gzip");
+    }
+
+    #[test]
+    fn test_decode_body_decodes_deflate() {
+        use std::io::Write;
+
+        let mut encoder =
+            flate2::write::DeflateEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all("hello, deflate".as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!(
+            decode_body(Some("deflate"), &compressed).unwrap(),
+            "hello, deflate"
+        );
+    }
+
+    #[test]
+    fn test_decode_body_decodes_brotli() {
+        use std::io::Write;
+
+        let mut compressed = Vec::new();
+        {
+            let mut encoder = brotli::CompressorWriter::new(&mut compressed, 4096, 5, 22);
+            encoder.write_all("hello, brotli".as_bytes()).unwrap();
+        }
+
+        assert_eq!(decode_body(Some("br"), &compressed).unwrap(), "hello, brotli");
+    }
+
+    fn pollutant(name: &str, data: Vec<Option<f32>>) -> Pollutant {
+        Pollutant {
+            name: name.to_string(),
+            unit: String::new(),
+            data,
+            grade: Grade::None,
+        }
+    }
+
+    #[test]
+    fn test_aqi_interpolates_within_a_breakpoint() {
+        // 55 lies in the PM10 breakpoint (31, 80) mapping to (51, 100): 51 + (55 - 31) = 75.
+        let p = pollutant("PM10", vec![Some(55.0)]);
+        assert_eq!(p.aqi(), Some(75));
+    }
+
+    #[test]
+    fn test_aqi_uses_the_most_recent_non_none_value() {
+        let p = pollutant("PM10", vec![Some(300.0), Some(20.0), None]);
+        assert_eq!(p.aqi(), Some(33));
+    }
+
+    #[test]
+    fn test_aqi_clamps_above_the_top_breakpoint() {
+        let p = pollutant("PM10", vec![Some(10000.0)]);
+        assert_eq!(p.aqi(), Some(500));
+    }
+
+    #[test]
+    fn test_aqi_is_none_without_any_measurement() {
+        let p = pollutant("PM10", vec![None, None]);
+        assert_eq!(p.aqi(), None);
+    }
+
+    #[test]
+    fn test_aqi_is_none_for_an_unindexed_pollutant() {
+        let p = pollutant("CAI", vec![Some(74.0)]);
+        assert_eq!(p.aqi(), None);
+    }
+
+    #[test]
+    fn test_overall_index_is_the_max_across_pollutants() {
+        let status = AirStatus {
+            station_address: String::new(),
+            time: String::new(),
+            pollutants: vec![
+                pollutant("PM10", vec![Some(20.0)]),
+                pollutant("SO2", vec![Some(1.0)]),
+                pollutant("CAI", vec![Some(74.0)]),
+            ],
+        };
+        assert_eq!(status.overall_index(), Some(500));
+    }
+
+    #[test]
+    fn test_overall_index_is_none_when_no_pollutant_is_indexed() {
+        let status = AirStatus {
+            station_address: String::new(),
+            time: String::new(),
+            pollutants: vec![pollutant("CAI", vec![Some(74.0)])],
+        };
+        assert_eq!(status.overall_index(), None);
+    }
+
+    #[test]
+    fn test_search_many_fetches_all_coordinates_concurrently() {
+        let service = hyper::service::make_service_fn(|_| {
+            hyper::service::service_fn_ok(|req| {
+                let query = req.uri().query().unwrap_or_default().to_string();
+                let html = format!(
+                    r#"<html><body><h1><span class="tit">{}</span><span class="tim">t</span></h1></body></html>"#,
+                    query
+                );
+                hyper::Response::new(hyper::Body::from(html))
+            })
+        });
+
+        let (shutdown_sender, shutdown_receiver) = futures::sync::oneshot::channel();
+        let mut rt = Runtime::new().unwrap();
+
+        let server = Server::bind(&"0.0.0.0:12126".parse().unwrap())
+            .serve(service)
+            .with_graceful_shutdown(shutdown_receiver)
+            .map_err(|why| panic!("{}", why));
+        rt.spawn(server);
+
+        let env_lock = ENV_AIRKOREA_URL_MUTEX.lock().unwrap();
+        std::env::set_var("AIRKOREA_URL", "http://localhost:12126");
+
+        let coords = vec![(1.0, 10.0), (2.0, 20.0), (3.0, 30.0)];
+        let results = rt.block_on(search_many(coords.clone()).collect()).unwrap();
+
+        std::env::remove_var("AIRKOREA_URL");
+        drop(env_lock);
+        shutdown_sender.send(()).unwrap();
+
+        let mut addresses = results
+            .into_iter()
+            .map(|s| s.station_address)
+            .collect::<Vec<_>>();
+        addresses.sort();
+
+        let mut expected = coords
+            .into_iter()
+            .map(|(lng, lat): (f32, f32)| format!("lng={}&lat={}", lng, lat))
+            .collect::<Vec<_>>();
+        expected.sort();
+
+        assert_eq!(addresses, expected);
+    }
 }
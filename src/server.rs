@@ -0,0 +1,86 @@
+//! A small warp-based HTTP service exposing [`search`](crate::search) as JSON.
+//!
+//! Enabled with the `server` cargo feature.
+//!
+//! ## Example
+//!
+//! ```no_run
+//! airkorea::server::run(([127, 0, 0, 1], 8080));
+//! ```
+
+use {
+    crate::search,
+    futures::prelude::*,
+    serde::Deserialize,
+    std::net::SocketAddr,
+    warp::Filter,
+};
+
+#[derive(Deserialize)]
+struct AirQuery {
+    lng: f32,
+    lat: f32,
+}
+
+fn air(query: AirQuery) -> impl Future<Item = impl warp::Reply, Error = warp::Rejection> {
+    search(query.lng, query.lat)
+        .map(|status| warp::reply::json(&status))
+        .map_err(|why| warp::reject::custom(why.compat()))
+}
+
+/// The `GET /air?lng=..&lat=..` filter, returning an [`AirStatus`](crate::AirStatus) as JSON.
+pub fn routes() -> impl Filter<Extract = (impl warp::Reply,), Error = warp::Rejection> + Clone {
+    warp::path("air")
+        .and(warp::path::end())
+        .and(warp::get2())
+        .and(warp::query::<AirQuery>())
+        .and_then(air)
+}
+
+/// Run the HTTP service, blocking the current thread.
+pub fn run(addr: impl Into<SocketAddr> + 'static) {
+    warp::serve(routes()).run(addr);
+}
+
+#[cfg(test)]
+mod tests {
+    use {super::*, hyper::Server, lazy_static::lazy_static, tokio::runtime::Runtime};
+
+    lazy_static! {
+        static ref ENV_AIRKOREA_URL_MUTEX: std::sync::Mutex<()> = std::sync::Mutex::new(());
+    }
+
+    #[test]
+    fn test_air_route_returns_air_status_json() {
+        static HTML: &'static str = "<html><body></body></html>";
+
+        let (shutdown_sender, shutdown_receiver) = futures::sync::oneshot::channel();
+        let mut rt = Runtime::new().unwrap();
+
+        let service = hyper::service::make_service_fn(|_| {
+            hyper::service::service_fn_ok(|_| hyper::Response::new(hyper::Body::from(HTML)))
+        });
+
+        let server = Server::bind(&"0.0.0.0:12122".parse().unwrap())
+            .serve(service)
+            .with_graceful_shutdown(shutdown_receiver)
+            .map_err(|why| panic!("{}", why));
+        rt.spawn(server);
+
+        let env_lock = ENV_AIRKOREA_URL_MUTEX.lock().unwrap();
+        std::env::set_var("AIRKOREA_URL", "http://localhost:12122");
+
+        let resp = warp::test::request()
+            .path("/air?lng=127.0&lat=37.0")
+            .reply(&routes());
+
+        std::env::remove_var("AIRKOREA_URL");
+        drop(env_lock);
+        shutdown_sender.send(()).unwrap();
+
+        assert_eq!(resp.status(), 200);
+        let body: crate::AirStatus = serde_json::from_slice(resp.body()).unwrap();
+        assert_eq!(body.station_address, "");
+        assert!(body.pollutants.is_empty());
+    }
+}